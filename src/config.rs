@@ -1,16 +1,39 @@
 use clap::ArgMatches;
+use regex::Regex;
 use reqwest::Url;
 use std::{env, fs, path::PathBuf};
 
+#[derive(Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    Text,
+    Json,
+    Csv,
+}
+
 #[derive(Clone)]
 pub struct ArgCollection {
     pub host: Url,
     pub file: PathBuf,
     pub list_external: bool,
     pub extract_robots_content: bool,
+    pub ignore_robots: bool,
+    pub max_depth: usize,
+    pub concurrency: usize,
+    pub retries: u32,
+    pub retry_backoff_ms: u64,
+    pub format: OutputFormat,
+    pub delay_ms: Option<u64>,
+    pub effective_delay_ms: u64,
+    pub include: Vec<Regex>,
+    pub exclude: Vec<Regex>,
     should_report_to_file: bool,
 }
 
+const DEFAULT_MAX_DEPTH: usize = usize::MAX;
+const DEFAULT_CONCURRENCY: usize = 10;
+const DEFAULT_RETRIES: u32 = 3;
+const DEFAULT_RETRY_BACKOFF_MS: u64 = 200;
+
 impl Default for ArgCollection {
     fn default() -> Self {
         ArgCollection {
@@ -18,6 +41,16 @@ impl Default for ArgCollection {
             file: PathBuf::new(),
             list_external: false,
             extract_robots_content: false,
+            ignore_robots: false,
+            max_depth: DEFAULT_MAX_DEPTH,
+            concurrency: DEFAULT_CONCURRENCY,
+            retries: DEFAULT_RETRIES,
+            retry_backoff_ms: DEFAULT_RETRY_BACKOFF_MS,
+            format: OutputFormat::Text,
+            delay_ms: None,
+            effective_delay_ms: 0,
+            include: Vec::new(),
+            exclude: Vec::new(),
             should_report_to_file: false,
         }
     }
@@ -55,6 +88,80 @@ impl ArgCollection {
             arg_collection.extract_robots_content = true;
         }
 
+        if arg_matches.is_present("ignore_robots") {
+            arg_collection.ignore_robots = true;
+        }
+
+        if let Some(max_depth) = arg_matches.value_of("max_depth") {
+            if let Ok(parsed) = max_depth.parse::<usize>() {
+                arg_collection.max_depth = parsed;
+            } else {
+                return Err("Failed to parse max-depth. Please provide a positive integer");
+            }
+        }
+
+        if let Some(concurrency) = arg_matches.value_of("concurrency") {
+            if let Ok(parsed) = concurrency.parse::<usize>() {
+                if parsed == 0 {
+                    return Err("Concurrency must be at least 1");
+                }
+                arg_collection.concurrency = parsed;
+            } else {
+                return Err("Failed to parse concurrency. Please provide a positive integer");
+            }
+        }
+
+        if let Some(retries) = arg_matches.value_of("retries") {
+            if let Ok(parsed) = retries.parse::<u32>() {
+                arg_collection.retries = parsed;
+            } else {
+                return Err("Failed to parse retries. Please provide a positive integer");
+            }
+        }
+
+        if let Some(retry_backoff_ms) = arg_matches.value_of("retry_backoff_ms") {
+            if let Ok(parsed) = retry_backoff_ms.parse::<u64>() {
+                arg_collection.retry_backoff_ms = parsed;
+            } else {
+                return Err("Failed to parse retry-backoff-ms. Please provide a positive integer");
+            }
+        }
+
+        if let Some(format) = arg_matches.value_of("format") {
+            arg_collection.format = match format {
+                "json" => OutputFormat::Json,
+                "csv" => OutputFormat::Csv,
+                _ => OutputFormat::Text,
+            };
+        }
+
+        if let Some(delay_ms) = arg_matches.value_of("delay_ms") {
+            if let Ok(parsed) = delay_ms.parse::<u64>() {
+                arg_collection.delay_ms = Some(parsed);
+                arg_collection.effective_delay_ms = parsed;
+            } else {
+                return Err("Failed to parse delay-ms. Please provide a positive integer");
+            }
+        }
+
+        if let Some(patterns) = arg_matches.values_of("include") {
+            for pattern in patterns {
+                match Regex::new(pattern) {
+                    Ok(regex) => arg_collection.include.push(regex),
+                    Err(_) => return Err("Failed to compile --include regex"),
+                }
+            }
+        }
+
+        if let Some(patterns) = arg_matches.values_of("exclude") {
+            for pattern in patterns {
+                match Regex::new(pattern) {
+                    Ok(regex) => arg_collection.exclude.push(regex),
+                    Err(_) => return Err("Failed to compile --exclude regex"),
+                }
+            }
+        }
+
         return Ok(arg_collection);
     }
 
@@ -80,6 +187,29 @@ impl ArgCollection {
         if self.extract_robots_content {
             println!("[~] Extracting robots.txt content");
         }
+        if self.ignore_robots {
+            println!("[~] Ignoring robots.txt rules");
+        }
+        if self.max_depth != DEFAULT_MAX_DEPTH {
+            println!("[~] Max crawl depth: {}", self.max_depth);
+        }
+        println!("[~] Concurrency: {}", self.concurrency);
+        println!(
+            "[~] Retries: {} (backoff base: {}ms)",
+            self.retries, self.retry_backoff_ms
+        );
+        if self.format != OutputFormat::Text {
+            println!("[~] Output format: {}", self.format.as_str());
+        }
+        if self.effective_delay_ms > 0 {
+            println!("[~] Inter-request delay: {}ms", self.effective_delay_ms);
+        }
+        for pattern in &self.include {
+            println!("[~] Including URLs matching: {}", pattern.as_str());
+        }
+        for pattern in &self.exclude {
+            println!("[~] Excluding URLs matching: {}", pattern.as_str());
+        }
 
         println!("\n");
     }
@@ -97,8 +227,44 @@ impl ArgCollection {
         if self.extract_robots_content {
             output.push_str(format!("[~] Extracting robots.txt content\n").as_str());
         }
+        if self.ignore_robots {
+            output.push_str(format!("[~] Ignoring robots.txt rules\n").as_str());
+        }
+        if self.max_depth != DEFAULT_MAX_DEPTH {
+            output.push_str(format!("[~] Max crawl depth: {}\n", self.max_depth).as_str());
+        }
+        output.push_str(format!("[~] Concurrency: {}\n", self.concurrency).as_str());
+        output.push_str(
+            format!(
+                "[~] Retries: {} (backoff base: {}ms)\n",
+                self.retries, self.retry_backoff_ms
+            )
+            .as_str(),
+        );
+        if self.format != OutputFormat::Text {
+            output.push_str(format!("[~] Output format: {}\n", self.format.as_str()).as_str());
+        }
+        if self.effective_delay_ms > 0 {
+            output.push_str(format!("[~] Inter-request delay: {}ms\n", self.effective_delay_ms).as_str());
+        }
+        for pattern in &self.include {
+            output.push_str(format!("[~] Including URLs matching: {}\n", pattern.as_str()).as_str());
+        }
+        for pattern in &self.exclude {
+            output.push_str(format!("[~] Excluding URLs matching: {}\n", pattern.as_str()).as_str());
+        }
 
         output.push_str("\n");
         return output;
     }
 }
+
+impl OutputFormat {
+    fn as_str(&self) -> &'static str {
+        match self {
+            OutputFormat::Text => "text",
+            OutputFormat::Json => "json",
+            OutputFormat::Csv => "csv",
+        }
+    }
+}
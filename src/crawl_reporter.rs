@@ -1,7 +1,9 @@
-use reqwest::Url;
 use std::{fs, io::Write};
 
-use crate::{config::ArgCollection, crawler::{CrawlResult, UrlType}};
+use crate::{
+    config::{ArgCollection, OutputFormat},
+    crawler::{CrawlResult, UrlType},
+};
 
 #[derive(Clone)]
 pub struct ReportInfo {
@@ -15,46 +17,199 @@ pub struct ReportInfo {
 pub fn report(report_info: ReportInfo) {
     //Create the file
     if let Ok(mut file) = fs::File::create(&report_info.config.file) {
-        let mut output = String::from(format!(
-            "[Micrawl report for {}] \n\n",
-            report_info.config.host
+        let output = match report_info.config.format {
+            OutputFormat::Json => build_json_report(&report_info),
+            OutputFormat::Csv => build_csv_report(&report_info),
+            OutputFormat::Text => build_text_report(&report_info),
+        };
+
+        //Show error if file can't be written
+        if let Err(_) = file.write_all(output.as_bytes()) {
+            eprintln!("[!] Failed writing output to file.");
+        }
+    } else {
+        eprintln!("[!] Failed to create output file.");
+    }
+}
+
+fn build_text_report(report_info: &ReportInfo) -> String {
+    let mut output = String::from(format!(
+        "[Micrawl report for {}] \n\n",
+        report_info.config.host
+    ));
+
+    //Append the config
+    output.push_str(&report_info.config.as_string());
+
+    //Append the robots.txt content if present
+    if let Some(content) = &report_info.robots {
+        output.push_str(&format!(
+            "=========== Robots.txt ===========\n{}\n==================================\n\n",
+            content
         ));
+    }
 
-        //Append the config
-        output.push_str(&report_info.config.as_string());
+    //Append all the crawled pages
+    for crawl_result in &report_info.discovered_links {
+        let mut prepend = "🔗";
+        if crawl_result.url_type == UrlType::External {
+            prepend = "↗";
+        }else if crawl_result.url_type == UrlType::Form {
+            prepend = "📝";
+        }
+        output.push_str(&format!("{} {} \n", prepend, crawl_result.url));
+    }
 
-        //Append the robots.txt content if present
-        if let Some(content) = &report_info.robots {
-            output.push_str(&format!(
-                "=========== Robots.txt ===========\n{}\n==================================\n\n",
-                content
-            ));
+    //Append the broken link report
+    let broken_links = find_broken_links(report_info);
+    output.push_str(&format!("\n=========== Broken links ({}) ===========\n", broken_links.len()));
+    if broken_links.is_empty() {
+        output.push_str("None found.\n");
+    } else {
+        for crawl_result in &broken_links {
+            let status = crawl_result
+                .status
+                .map(|code| code.to_string())
+                .unwrap_or_else(|| "unreachable".to_string());
+            let found_on = crawl_result
+                .found_on
+                .as_ref()
+                .map(|url| url.to_string())
+                .unwrap_or_else(|| "-".to_string());
+            output.push_str(&format!("[{}] {} (found on {})\n", status, crawl_result.url, found_on));
         }
+    }
+    output.push_str("==========================================\n");
+
+    //Append the final info (amount of crawled pages and the elapsed time)
+    output.push_str(&format!(
+        "\nFound {} links in {}.{} sec. {} broken link(s).",
+        report_info.discovered_links.len(),
+        report_info.elapsed_secs,
+        report_info.elapsed_ms,
+        broken_links.len()
+    ));
 
-        //Append all the crawled pages
-        for crawl_result in &report_info.discovered_links {
-            let mut prepend = "🔗";
-            if crawl_result.url_type == UrlType::External {
-                prepend = "↗";
-            }else if crawl_result.url_type == UrlType::Form {
-                prepend = "📝";
-            }
-            output.push_str(&format!("{} {} \n", prepend, crawl_result.url));
+    return output;
+}
+
+fn build_json_report(report_info: &ReportInfo) -> String {
+    let mut findings = String::new();
+    for (i, crawl_result) in report_info.discovered_links.iter().enumerate() {
+        if i > 0 {
+            findings.push(',');
         }
+        let status = crawl_result
+            .status
+            .map(|code| code.to_string())
+            .unwrap_or_else(|| "null".to_string());
+        findings.push_str(&format!(
+            "{{\"url\":\"{}\",\"url_type\":\"{}\",\"status\":{}}}",
+            json_escape(crawl_result.url.as_str()),
+            url_type_str(&crawl_result.url_type),
+            status
+        ));
+    }
+
+    let robots_json = match &report_info.robots {
+        Some(content) => format!("\"{}\"", json_escape(content)),
+        None => "null".to_string(),
+    };
+
+    //Separate numeric fields rather than concatenating elapsed_secs and
+    //elapsed_ms into a fake decimal - "3 sec 5 ms" must not render as `3.5`,
+    //which downstream tooling would misread as 3.5 seconds.
+    return format!(
+        "{{\"host\":\"{}\",\"config\":{},\"robots\":{},\"elapsed_secs\":{},\"elapsed_ms\":{},\"findings\":[{}]}}",
+        json_escape(report_info.config.host.as_str()),
+        build_config_json(&report_info.config),
+        robots_json,
+        report_info.elapsed_secs,
+        report_info.elapsed_ms,
+        findings
+    );
+}
+
+fn build_config_json(config: &ArgCollection) -> String {
+    return format!(
+        "{{\"list_external\":{},\"extract_robots_content\":{},\"ignore_robots\":{},\"max_depth\":{},\"concurrency\":{},\"retries\":{},\"retry_backoff_ms\":{}}}",
+        config.list_external,
+        config.extract_robots_content,
+        config.ignore_robots,
+        config.max_depth,
+        config.concurrency,
+        config.retries,
+        config.retry_backoff_ms,
+    );
+}
 
-        //Append the final info (amount of crawled pages and the elapsed time)
+fn build_csv_report(report_info: &ReportInfo) -> String {
+    let mut output = String::from("url,url_type,status\n");
+    for crawl_result in &report_info.discovered_links {
+        let status = crawl_result
+            .status
+            .map(|code| code.to_string())
+            .unwrap_or_default();
         output.push_str(&format!(
-            "\nFound {} links in {}.{} sec.",
-            report_info.discovered_links.len(),
-            report_info.elapsed_secs,
-            report_info.elapsed_ms
+            "{},{},{}\n",
+            csv_escape(crawl_result.url.as_str()),
+            url_type_str(&crawl_result.url_type),
+            status
         ));
+    }
+    return output;
+}
 
-        //Show error if file can't be written
-        if let Err(_) = file.write_all(output.as_bytes()) {
-            eprintln!("[!] Failed writing output to file.");
+fn url_type_str(url_type: &UrlType) -> &'static str {
+    match url_type {
+        UrlType::Link => "link",
+        UrlType::Form => "form",
+        UrlType::External => "external",
+    }
+}
+
+fn json_escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
         }
-    } else {
-        eprintln!("[!] Failed to create output file.");
     }
+    return escaped;
+}
+
+fn csv_escape(value: &str) -> String {
+    if value.contains(',') || value.contains('"') || value.contains('\n') {
+        return format!("\"{}\"", value.replace('"', "\"\""));
+    }
+    return value.to_string();
+}
+
+//Finds every 4xx/5xx or unreachable URL. Forms aren't fetched so they're
+//never broken links, and external links are only checked (and thus only
+//ever judged broken) when list_external was requested. Links that were
+//merely discovered but never fetched (wrong extension, too deep,
+//disallowed by robots.txt, ...) are not
+//broken links - `fetched` distinguishes that from an actual failed fetch.
+fn find_broken_links(report_info: &ReportInfo) -> Vec<CrawlResult> {
+    return report_info
+        .discovered_links
+        .iter()
+        .filter(|crawl_result| crawl_result.url_type != UrlType::Form)
+        .filter(|crawl_result| {
+            crawl_result.url_type != UrlType::External || report_info.config.list_external
+        })
+        .filter(|crawl_result| crawl_result.fetched)
+        .filter(|crawl_result| match crawl_result.status {
+            Some(code) => code >= 400,
+            None => true,
+        })
+        .cloned()
+        .collect();
 }
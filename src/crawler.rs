@@ -1,11 +1,15 @@
 use crate::{config::ArgCollection, crawl_reporter, robots};
-use futures::stream::FuturesUnordered;
-use reqwest::{Error, Response};
+use futures::stream::{self, StreamExt};
+use rand::Rng;
+use reqwest::{header::RETRY_AFTER, Client, Error, Response};
 use select::{document::Document, predicate::Name};
-use std::time::Instant;
-use tokio::task::JoinHandle;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex as AsyncMutex;
 use url::Url;
 
+const MAX_BACKOFF_MS: u64 = 10_000;
+
 #[derive(PartialEq, Clone)]
 pub enum UrlType {
     Link,
@@ -13,19 +17,27 @@ pub enum UrlType {
     External
 }
 pub struct Crawler {
-    queue: Vec<Url>,
-    crawled_pages: Vec<Url>,
+    queue: Vec<(Url, usize)>,
+    crawled_pages: Vec<(Url, usize)>,
     block_list: Vec<Url>,
     discovered_links: Vec<CrawlResult>,
     config: ArgCollection,
     start_time: Instant,
     robots_content: Option<String>,
+    robots_rules: robots::RobotsRules,
 }
 
 #[derive(PartialEq, Clone)]
 pub struct CrawlResult {
     pub url: Url,
-    pub url_type: UrlType
+    pub url_type: UrlType,
+    pub status: Option<u16>,
+    //Whether a request for this URL was actually attempted. `status: None`
+    //only means "failed" when this is true - otherwise the URL was simply
+    //discovered and never fetched (wrong extension, too deep, disallowed by
+    //robots.txt, ...). Out-of-scope URLs never get an entry at all.
+    pub fetched: bool,
+    pub found_on: Option<Url>,
 }
 
 impl Crawler {
@@ -38,62 +50,107 @@ impl Crawler {
             config: arg_collection,
             start_time: Instant::now(),
             robots_content: None,
+            robots_rules: robots::RobotsRules::default(),
         };
 
-        //Add initial url to the queue.
+        //Add initial url to the queue, at depth 0.
         crawler
             .queue
-            .push(Url::parse(crawler.config.host.as_str()).unwrap());
+            .push((Url::parse(crawler.config.host.as_str()).unwrap(), 0));
 
         return crawler;
     }
 
     pub async fn crawl(&mut self) {
-        if self.config.extract_robots_content {
-            if let Some(robots) = robots::try_extract(&self.config.host) {
-                self.print_robots_content(&robots);
-                self.robots_content = Some(robots);
+        if !self.config.ignore_robots {
+            if let Some(robots_content) = robots::try_extract(&self.config.host) {
+                self.robots_rules = robots::RobotsRules::parse(&robots_content);
+                if self.config.extract_robots_content {
+                    self.print_robots_content(&robots_content);
+                    self.robots_content = Some(robots_content);
+                }
             }
         }
 
+        //The seed URL is pushed onto the queue in `new()`, before robots.txt
+        //has been fetched - enforce the same robots check on it here so a
+        //Disallow covering the host root stops Micrawl before its very
+        //first request, same as it would for any other discovered URL.
+        if !self.is_allowed_by_robots(&self.config.host) {
+            self.queue.clear();
+        }
+
+        //The robots-derived Crawl-delay wins unless --delay-ms was explicitly
+        //set to something larger.
+        let robots_delay_ms = self.robots_rules.crawl_delay().map(|secs| (secs * 1000.0) as u64);
+        self.config.effective_delay_ms = match (self.config.delay_ms, robots_delay_ms) {
+            (Some(configured), Some(robots)) => configured.max(robots),
+            (Some(configured), None) => configured,
+            (None, Some(robots)) => robots,
+            (None, None) => 0,
+        };
+        //The config echo printed before crawl() runs can only reflect an
+        //explicit --delay-ms, since robots.txt (and any Crawl-delay it
+        //declares) isn't fetched yet at that point. Re-echo the delay now
+        //that it also accounts for robots.txt, so the startup output
+        //reflects what the crawler will actually use.
+        if self.config.effective_delay_ms > 0 {
+            println!(
+                "[~] Inter-request delay: {}ms\n",
+                self.config.effective_delay_ms
+            );
+        }
+        let rate_limiter = RateLimiter::new(Duration::from_millis(self.config.effective_delay_ms));
+
         //Don't want to match on Ok or Error here. Just panic if no client can be constructed.
         // let client = reqwest::ClientBuilder::new()
         //     .redirect(Policy::none())
         //     .build().unwrap();
-        
+
         let client = reqwest::Client::new(); //Create single Client and clone that so we make use of the connection pool. https://docs.rs/reqwest/0.10.9/reqwest/struct.Client.html
         loop {
             if self.queue.is_empty() {
                 break;
             }
 
-            let tasks = FuturesUnordered::new();
-            while let Some(current) = self.queue.pop() {
-                let client_clone = client.clone();
-                self.crawled_pages.push(current.clone());
-                let handle: JoinHandle<Result<Response, Error>> = tokio::spawn(async move {
-                    let result = client_clone
-                        .get(current)
-                        .header("User-Agent", randua::new().to_string())
-                        .send()
-                        .await;
-                    return result;
-                });
-                tasks.push(handle);
+            let batch: Vec<(Url, usize)> = self.queue.drain(..).collect();
+            for entry in &batch {
+                self.crawled_pages.push(entry.clone());
             }
 
-            if tasks.len() < 1 {
-                break;
-            }
+            let retries = self.config.retries;
+            let backoff_ms = self.config.retry_backoff_ms;
 
-            // await all tasks here.
-            let results = futures::future::join_all(tasks).await;
-            for result in results {
-                if let Ok(unwrapped) = result {
-                    if let Ok(response) = unwrapped {
-                        // self.crawled_pages.push(response.url().clone()); //Register this URL as crawled by adding it to the list.
+            //Fetch at most `concurrency` requests at once, instead of draining
+            //the whole queue into one unbounded batch of futures.
+            let results: Vec<(Url, usize, Result<Response, Error>)> = stream::iter(batch)
+                .map(|(url, depth)| {
+                    let client_clone = client.clone();
+                    let rate_limiter = rate_limiter.clone();
+                    async move {
+                        rate_limiter.wait_turn().await;
+                        let result =
+                            fetch_with_retry(&client_clone, url.clone(), retries, backoff_ms).await;
+                        return (url, depth, result);
+                    }
+                })
+                .buffer_unordered(self.config.concurrency)
+                .collect()
+                .await;
 
+            for (requested_url, depth, result) in results {
+                match result {
+                    Err(_) => {
+                        self.record_status(&requested_url, None);
+                    }
+                    Ok(response) => {
                         let from_url = response.url().clone(); //Clone here because response.text() consumes the object.
+                        //Key on the requested (pre-redirect) URL, not `from_url`: that's
+                        //how the entry was keyed when it was discovered, so keying the
+                        //update the same way avoids missing it on a redirect and pushing
+                        //a duplicate entry for the final URL instead.
+                        self.record_status(&requested_url, Some(response.status().as_u16()));
+
                         if response.status().is_success() {
                             if let Ok(text) = response.text().await {
                                 let doc = Document::from(text.as_str());
@@ -110,20 +167,28 @@ impl Crawler {
                                         }
                                     }
 
-                                    if self.should_enqueue(&url) {
-                                        self.queue.push(url.clone());
+                                    if self.should_enqueue(&url, depth + 1) {
+                                        self.queue.push((url.clone(), depth + 1));
                                     }
 
- 
 
-                                    if !self.discovered_links.iter().any(|elem| &elem.url == &url) {
+
+                                    if self.is_in_scope(&url)
+                                        && !self.discovered_links.iter().any(|elem| &elem.url == &url)
+                                    {
                                         let mut crawl_result = CrawlResult {
                                             url,
-                                            url_type: UrlType::Link
+                                            url_type: UrlType::Link,
+                                            status: None,
+                                            fetched: false,
+                                            found_on: Some(from_url.clone()),
                                         };
                                         if self.is_external(&crawl_result.url) {
                                             if self.config.list_external {
                                                 crawl_result.url_type = UrlType::External;
+                                                crawl_result.status =
+                                                    check_external_status(&client, &crawl_result.url).await;
+                                                crawl_result.fetched = true;
                                                 self.discovered_links.push(crawl_result);
                                             }
                                         } else {
@@ -140,7 +205,10 @@ impl Crawler {
                                         if !self.discovered_links.iter().any(|elem| &elem.url == &url) {
                                             let crawl_result = CrawlResult {
                                                 url,
-                                                url_type: UrlType::Form
+                                                url_type: UrlType::Form,
+                                                status: None,
+                                                fetched: false,
+                                                found_on: Some(from_url.clone()),
                                             };
                                             self.discovered_links.push(crawl_result);
                                         }
@@ -190,12 +258,32 @@ impl Crawler {
         return results;
     }
 
-    fn should_enqueue(&mut self, url: &Url) -> bool {
-        return !self.already_crawled(url)
+    fn should_enqueue(&mut self, url: &Url, depth: usize) -> bool {
+        return depth <= self.config.max_depth
+            && !self.already_crawled(url)
             && !self.is_in_queue(url)
             && !self.is_in_blocklist(url)
             && !self.is_external(url)
-            && self.is_webpage(url);
+            && self.is_webpage(url)
+            && self.is_allowed_by_robots(url)
+            && self.is_in_scope(url);
+    }
+
+    fn is_allowed_by_robots(&self, url: &Url) -> bool {
+        if self.config.ignore_robots {
+            return true;
+        }
+        return self.robots_rules.is_allowed(url.path());
+    }
+
+    //A URL is in scope if it matches at least one --include pattern (when any
+    //are given) and matches no --exclude pattern.
+    fn is_in_scope(&self, url: &Url) -> bool {
+        let url_str = url.as_str();
+        let included = self.config.include.is_empty()
+            || self.config.include.iter().any(|pattern| pattern.is_match(url_str));
+        let excluded = self.config.exclude.iter().any(|pattern| pattern.is_match(url_str));
+        return included && !excluded;
     }
 
     fn is_in_blocklist(&self, url: &Url) -> bool {
@@ -220,14 +308,17 @@ impl Crawler {
     }
 
     fn is_in_queue(&self, url: &Url) -> bool {
-        return self.queue.iter().any(|elem| elem.as_str() == url.as_str());
+        return self
+            .queue
+            .iter()
+            .any(|(elem, _)| elem.as_str() == url.as_str());
     }
 
     fn already_crawled(&self, url: &Url) -> bool {
         return self
             .crawled_pages
             .iter()
-            .any(|elem| elem.as_str() == url.as_str());
+            .any(|(elem, _)| elem.as_str() == url.as_str());
     }
 
     fn is_external(&self, url: &Url) -> bool {
@@ -235,13 +326,40 @@ impl Crawler {
     }
 
     fn should_print(&self, url: &Url) -> bool {
-        return !self.discovered_links.iter().any(|elem| &elem.url == url);
+        return self.is_in_scope(url)
+            && !self.discovered_links.iter().any(|elem| &elem.url == url);
+    }
+
+    //Records the status of a fetched URL on its existing CrawlResult entry,
+    //or adds one (e.g. for the root URL, which is never "discovered" via an
+    //anchor or form).
+    fn record_status(&mut self, url: &Url, status: Option<u16>) {
+        if let Some(entry) = self.discovered_links.iter_mut().find(|elem| &elem.url == url) {
+            entry.status = status;
+            entry.fetched = true;
+            return;
+        }
+
+        let url_type = if self.is_external(url) {
+            UrlType::External
+        } else {
+            UrlType::Link
+        };
+        self.discovered_links.push(CrawlResult {
+            url: url.clone(),
+            url_type,
+            status,
+            fetched: true,
+            found_on: None,
+        });
     }
 
     fn is_same_domain(&self, url: &Url) -> bool {
         if let Some(base_domain) = self.config.host.domain() {
             if let Some(domain) = url.domain() {
-                if domain.contains(base_domain) {
+                //Exact match, not substring - "evil-example.com" must not be
+                //treated as the same domain as "example.com".
+                if domain == base_domain {
                     return true;
                 }
             }
@@ -282,3 +400,122 @@ impl Crawler {
         println!("");
     }
 }
+
+/// A shared token/timestamp gate that spaces out successive requests by at
+/// least `delay` without serializing the whole `buffer_unordered` pipeline:
+/// each caller reserves the next free slot and only sleeps for its own
+/// share of the wait.
+#[derive(Clone)]
+struct RateLimiter {
+    delay: Duration,
+    next_slot: Arc<AsyncMutex<Instant>>,
+}
+
+impl RateLimiter {
+    fn new(delay: Duration) -> RateLimiter {
+        RateLimiter {
+            delay,
+            next_slot: Arc::new(AsyncMutex::new(Instant::now())),
+        }
+    }
+
+    async fn wait_turn(&self) {
+        if self.delay.is_zero() {
+            return;
+        }
+
+        let scheduled_at = {
+            let mut next_slot = self.next_slot.lock().await;
+            let now = Instant::now();
+            let scheduled_at = if *next_slot > now { *next_slot } else { now };
+            *next_slot = scheduled_at + self.delay;
+            scheduled_at
+        };
+
+        let now = Instant::now();
+        if scheduled_at > now {
+            tokio::time::sleep(scheduled_at - now).await;
+        }
+    }
+}
+
+/// Sends a GET request, retrying transient failures (connection/timeout
+/// errors and 5xx/429 responses) up to `retries` times with exponential
+/// backoff plus jitter. Honors a `Retry-After` header on 429 responses when
+/// present.
+async fn fetch_with_retry(
+    client: &Client,
+    url: Url,
+    retries: u32,
+    base_delay_ms: u64,
+) -> Result<Response, Error> {
+    let mut attempt = 0;
+    loop {
+        let result = client
+            .get(url.clone())
+            .header("User-Agent", randua::new().to_string())
+            .send()
+            .await;
+
+        let is_transient = match &result {
+            Ok(response) => {
+                let status = response.status();
+                status.is_server_error() || status.as_u16() == 429
+            }
+            Err(_) => true,
+        };
+
+        if !is_transient || attempt >= retries {
+            return result;
+        }
+
+        let retry_after = result
+            .as_ref()
+            .ok()
+            .and_then(|response| response.headers().get(RETRY_AFTER))
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<u64>().ok())
+            .map(Duration::from_secs);
+
+        let delay = retry_after.unwrap_or_else(|| backoff_delay(attempt, base_delay_ms));
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+/// Issues a lightweight HEAD request to check whether an external link is
+/// reachable. Some servers reject HEAD (405/501) even though they serve the
+/// same URL fine on GET, so a HEAD 4xx or 501 falls back to a GET before the link
+/// is judged broken.
+async fn check_external_status(client: &Client, url: &Url) -> Option<u16> {
+    let head_result = client
+        .head(url.clone())
+        .header("User-Agent", randua::new().to_string())
+        .send()
+        .await;
+
+    match head_result {
+        Ok(response)
+            if response.status().is_client_error() || response.status().as_u16() == 501 =>
+        {
+            match client
+                .get(url.clone())
+                .header("User-Agent", randua::new().to_string())
+                .send()
+                .await
+            {
+                Ok(response) => Some(response.status().as_u16()),
+                Err(_) => Some(response.status().as_u16()),
+            }
+        }
+        Ok(response) => Some(response.status().as_u16()),
+        Err(_) => None,
+    }
+}
+
+fn backoff_delay(attempt: u32, base_delay_ms: u64) -> Duration {
+    let exponential = base_delay_ms.saturating_mul(1u64 << attempt.min(16));
+    let capped_ms = exponential.min(MAX_BACKOFF_MS);
+    let jitter_ms = rand::thread_rng().gen_range(0..=capped_ms / 4 + 1);
+    return Duration::from_millis(capped_ms + jitter_ms);
+}
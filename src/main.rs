@@ -41,6 +41,69 @@ async fn main() {
             .help("Extract content from robots.txt.")
             .takes_value(false)
             .required(false))
+        .arg(Arg::with_name("ignore_robots")
+            .short("i")
+            .long("ignore-robots")
+            .value_name("ignore_robots")
+            .help("Ignore robots.txt rules and crawl paths that would otherwise be disallowed. Only use this on targets you're authorized to test.")
+            .takes_value(false)
+            .required(false))
+        .arg(Arg::with_name("max_depth")
+            .short("d")
+            .long("max-depth")
+            .value_name("max_depth")
+            .help("Limits how many links deep from the starting URL Micrawl will follow.")
+            .takes_value(true)
+            .required(false))
+        .arg(Arg::with_name("concurrency")
+            .short("c")
+            .long("concurrency")
+            .value_name("concurrency")
+            .help("Limits how many requests may be in flight at once. Defaults to 10.")
+            .takes_value(true)
+            .required(false))
+        .arg(Arg::with_name("retries")
+            .long("retries")
+            .value_name("retries")
+            .help("Number of times to retry a request after a transient failure (connection error, timeout, 5xx or 429). Defaults to 3.")
+            .takes_value(true)
+            .required(false))
+        .arg(Arg::with_name("retry_backoff_ms")
+            .long("retry-backoff-ms")
+            .value_name("retry_backoff_ms")
+            .help("Base delay in milliseconds for exponential retry backoff. Defaults to 200.")
+            .takes_value(true)
+            .required(false))
+        .arg(Arg::with_name("format")
+            .short("f")
+            .long("format")
+            .value_name("format")
+            .help("Output format for the report file. Defaults to text.")
+            .takes_value(true)
+            .possible_values(&["text", "json", "csv"])
+            .required(false))
+        .arg(Arg::with_name("delay_ms")
+            .long("delay-ms")
+            .value_name("delay_ms")
+            .help("Minimum delay in milliseconds between successive requests to the host. A robots.txt Crawl-delay wins unless this is explicitly larger.")
+            .takes_value(true)
+            .required(false))
+        .arg(Arg::with_name("include")
+            .long("include")
+            .value_name("include")
+            .help("Only crawl/print URLs matching this regex. Repeatable; a URL is in scope if it matches any --include pattern.")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .required(false))
+        .arg(Arg::with_name("exclude")
+            .long("exclude")
+            .value_name("exclude")
+            .help("Never crawl/print URLs matching this regex. Repeatable.")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .required(false))
             .get_matches();
 
     match ArgCollection::parse(matches) {
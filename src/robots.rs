@@ -2,6 +2,183 @@ use std::time::Duration;
 
 use reqwest::Url;
 
+/// The user-agent token Micrawl identifies itself with when selecting which
+/// robots.txt group applies to it. This is independent of the (randomized)
+/// `User-Agent` header sent with individual requests.
+const CRAWLER_AGENT: &str = "Micrawl";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RuleKind {
+    Allow,
+    Disallow,
+}
+
+#[derive(Debug, Clone)]
+struct Rule {
+    kind: RuleKind,
+    prefix: String,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Group {
+    agents: Vec<String>,
+    rules: Vec<Rule>,
+    crawl_delay: Option<f64>,
+}
+
+impl Group {
+    fn matches_agent(&self, agent: &str) -> bool {
+        self.agents
+            .iter()
+            .any(|a| a.eq_ignore_ascii_case(agent))
+    }
+
+    fn is_wildcard(&self) -> bool {
+        self.agents.iter().any(|a| a == "*")
+    }
+}
+
+/// A compiled set of robots.txt rules, scoped to the group that applies to
+/// our crawler (falling back to the `*` group when no specific group
+/// matches).
+#[derive(Debug, Clone, Default)]
+pub struct RobotsRules {
+    rules: Vec<Rule>,
+    crawl_delay: Option<f64>,
+}
+
+impl RobotsRules {
+    /// Parses the raw contents of a robots.txt file into rules scoped to
+    /// Micrawl's user-agent group.
+    pub fn parse(content: &str) -> RobotsRules {
+        let groups = parse_groups(content);
+
+        let selected = groups
+            .iter()
+            .find(|group| group.matches_agent(CRAWLER_AGENT))
+            .or_else(|| groups.iter().find(|group| group.is_wildcard()));
+
+        match selected {
+            Some(group) => RobotsRules {
+                rules: group.rules.clone(),
+                crawl_delay: group.crawl_delay,
+            },
+            None => RobotsRules::default(),
+        }
+    }
+
+    /// Tests a path against the compiled rules using longest-match-wins
+    /// semantics: the most specific matching prefix decides, with `Allow`
+    /// beating `Disallow` on equal length. A path with no matching rule is
+    /// allowed.
+    pub fn is_allowed(&self, path: &str) -> bool {
+        let mut best: Option<&Rule> = None;
+        for rule in &self.rules {
+            if !path.starts_with(rule.prefix.as_str()) {
+                continue;
+            }
+            best = match best {
+                Some(current) if current.prefix.len() > rule.prefix.len() => Some(current),
+                Some(current)
+                    if current.prefix.len() == rule.prefix.len()
+                        && current.kind == RuleKind::Allow =>
+                {
+                    Some(current)
+                }
+                _ => Some(rule),
+            };
+        }
+
+        match best {
+            Some(rule) => rule.kind == RuleKind::Allow,
+            None => true,
+        }
+    }
+
+    /// Returns the `Crawl-delay` (in seconds) declared for our group, if any.
+    pub fn crawl_delay(&self) -> Option<f64> {
+        self.crawl_delay
+    }
+}
+
+fn parse_groups(content: &str) -> Vec<Group> {
+    let mut groups: Vec<Group> = Vec::new();
+    let mut current: Option<Group> = None;
+    let mut seen_rule_since_agent = false;
+
+    for raw_line in content.lines() {
+        let line = strip_comment(raw_line).trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let (key, value) = match line.split_once(':') {
+            Some((k, v)) => (k.trim().to_ascii_lowercase(), v.trim()),
+            None => continue,
+        };
+
+        match key.as_str() {
+            "user-agent" => {
+                //A new User-agent line right after rules starts a new group;
+                //consecutive User-agent lines (no rules in between) extend
+                //the same group.
+                if current.is_none() || seen_rule_since_agent {
+                    if let Some(group) = current.take() {
+                        groups.push(group);
+                    }
+                    current = Some(Group::default());
+                    seen_rule_since_agent = false;
+                }
+                if let Some(group) = current.as_mut() {
+                    group.agents.push(value.to_string());
+                }
+            }
+            "disallow" => {
+                seen_rule_since_agent = true;
+                if value.is_empty() {
+                    //An empty Disallow means "allow all" - nothing to record.
+                    continue;
+                }
+                if let Some(group) = current.as_mut() {
+                    group.rules.push(Rule {
+                        kind: RuleKind::Disallow,
+                        prefix: value.to_string(),
+                    });
+                }
+            }
+            "allow" => {
+                seen_rule_since_agent = true;
+                if let Some(group) = current.as_mut() {
+                    group.rules.push(Rule {
+                        kind: RuleKind::Allow,
+                        prefix: value.to_string(),
+                    });
+                }
+            }
+            "crawl-delay" => {
+                seen_rule_since_agent = true;
+                if let Some(group) = current.as_mut() {
+                    group.crawl_delay = value.parse::<f64>().ok();
+                }
+            }
+            _ => continue,
+        }
+    }
+
+    if let Some(group) = current.take() {
+        groups.push(group);
+    }
+
+    return groups;
+}
+
+fn strip_comment(line: &str) -> &str {
+    match line.find('#') {
+        Some(idx) => &line[..idx],
+        None => line,
+    }
+}
+
 pub fn try_extract(base_url: &Url) -> Option<String> {
     if let Ok(url) = base_url.join("robots.txt") {
         return get_robots_content(url);